@@ -0,0 +1,194 @@
+//! ASCII rendering: grayscale ramp mapping, an optional Sobel edge overlay,
+//! and an optional truecolor sample per cell.
+//!
+//! `process_frame` always starts from the same grayscale ramp lookup as
+//! before; the edge overlay and truecolor sampling are additive and only
+//! run when the caller's config turns them on, so the default path is the
+//! original flat grayscale art.
+
+use color_eyre::Result;
+use opencv::core::{Vec3b, CV_32F};
+use opencv::{imgproc, prelude::*};
+
+// Sobel gradient magnitude above which a cell's character is replaced by a
+// directional edge glyph instead of its ramp value.
+const EDGE_MAGNITUDE_THRESHOLD: f64 = 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AsciiCell {
+    pub ch: char,
+    pub color: Option<(u8, u8, u8)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AsciiFrame {
+    pub rows: Vec<Vec<AsciiCell>>,
+}
+
+impl AsciiFrame {
+    /// Flattens the frame back to plain text, dropping any color, for the
+    /// video Mat renderer which only ever draws white-on-black.
+    pub fn plain_text(&self) -> String {
+        self.rows
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.ch).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Renders to a ratatui `Text`, run-length-encoding consecutive cells
+    /// that share a style into a single span so a plain grayscale frame
+    /// costs one span per line rather than one per character.
+    pub fn to_text(&self) -> ratatui::text::Text<'static> {
+        use ratatui::style::{Color, Style};
+        use ratatui::text::{Line, Span};
+
+        let lines = self
+            .rows
+            .iter()
+            .map(|row| {
+                let mut spans = Vec::new();
+                let mut run: Option<(Style, String)> = None;
+
+                for cell in row {
+                    let style = match cell.color {
+                        Some((r, g, b)) => Style::default().fg(Color::Rgb(r, g, b)),
+                        None => Style::default(),
+                    };
+
+                    match &mut run {
+                        Some((run_style, text)) if *run_style == style => text.push(cell.ch),
+                        _ => {
+                            if let Some((run_style, text)) = run.take() {
+                                spans.push(Span::styled(text, run_style));
+                            }
+                            run = Some((style, cell.ch.to_string()));
+                        }
+                    }
+                }
+
+                if let Some((run_style, text)) = run {
+                    spans.push(Span::styled(text, run_style));
+                }
+
+                Line::from(spans)
+            })
+            .collect::<Vec<_>>();
+
+        ratatui::text::Text::from(lines)
+    }
+}
+
+pub fn get_ascii_char(value: u8, ramp: &[char]) -> char {
+    let index = (value as usize * (ramp.len() - 1)) / 255;
+
+    if index < ramp.len() {
+        ramp[index]
+    } else {
+        ramp[ramp.len() - 1]
+    }
+}
+
+/// Maps a Sobel gradient angle (degrees, any sign/range) to the glyph for
+/// the edge running perpendicular to that gradient.
+fn edge_glyph(gradient_angle_deg: f64) -> char {
+    let angle = gradient_angle_deg.rem_euclid(180.0);
+
+    if !(22.5..157.5).contains(&angle) {
+        '|'
+    } else if angle < 67.5 {
+        '/'
+    } else if angle < 112.5 {
+        '-'
+    } else {
+        '\\'
+    }
+}
+
+pub fn process_frame(
+    frame: &Mat,
+    ramp: &[char],
+    edge_overlay: bool,
+    truecolor: bool,
+) -> opencv::Result<AsciiFrame> {
+    let mut gray = Mat::default();
+    imgproc::cvt_color(frame, &mut gray, imgproc::COLOR_BGR2GRAY, 0)?;
+
+    let (rows, cols) = (gray.rows(), gray.cols());
+
+    if rows == 0 || cols == 0 {
+        return Ok(AsciiFrame::default());
+    }
+
+    let edges = if edge_overlay {
+        let mut grad_x = Mat::default();
+        let mut grad_y = Mat::default();
+        imgproc::sobel(&gray, &mut grad_x, CV_32F, 1, 0, 3, 1.0, 0.0, opencv::core::BORDER_DEFAULT)?;
+        imgproc::sobel(&gray, &mut grad_y, CV_32F, 0, 1, 3, 1.0, 0.0, opencv::core::BORDER_DEFAULT)?;
+
+        let mut magnitude = Mat::default();
+        let mut angle = Mat::default();
+        opencv::core::cart_to_polar(&grad_x, &grad_y, &mut magnitude, &mut angle, true)?;
+
+        Some((magnitude, angle))
+    } else {
+        None
+    };
+
+    let frame_rows = (0..rows)
+        .map(|y| {
+            (0..cols)
+                .map(|x| {
+                    let gray_value = *gray.at_2d::<u8>(y, x).unwrap_or(&0);
+                    let mut ch = get_ascii_char(gray_value, ramp);
+
+                    if let Some((magnitude, angle)) = &edges {
+                        let mag = *magnitude.at_2d::<f32>(y, x).unwrap_or(&0.0) as f64;
+
+                        if mag > EDGE_MAGNITUDE_THRESHOLD {
+                            let ang = *angle.at_2d::<f32>(y, x).unwrap_or(&0.0) as f64;
+                            ch = edge_glyph(ang);
+                        }
+                    }
+
+                    let color = if truecolor {
+                        frame
+                            .at_2d::<Vec3b>(y, x)
+                            .ok()
+                            .map(|bgr| (bgr[2], bgr[1], bgr[0]))
+                    } else {
+                        None
+                    };
+
+                    AsciiCell { ch, color }
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    Ok(AsciiFrame { rows: frame_rows })
+}
+
+pub fn render_ascii_frame(ascii: &str, size: opencv::core::Size, font_scale: f64) -> Result<Mat> {
+    let mut img =
+        Mat::new_size_with_default(size, opencv::core::CV_8UC3, opencv::core::Scalar::all(0.0))?;
+    let font = opencv::imgproc::FONT_HERSHEY_PLAIN;
+    let thickness = 1;
+    let color = opencv::core::Scalar::new(255.0, 255.0, 255.0, 0.0);
+
+    for (i, line) in ascii.lines().enumerate() {
+        imgproc::put_text(
+            &mut img,
+            line,
+            opencv::core::Point::new(0, (i as i32 + 1) * 10),
+            font,
+            font_scale,
+            color,
+            thickness,
+            imgproc::LINE_8,
+            false,
+        )?;
+    }
+
+    Ok(img)
+}
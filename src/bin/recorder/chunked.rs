@@ -0,0 +1,244 @@
+//! Scene-aware chunked parallel video encoding.
+//!
+//! The capture loop buffers rendered ASCII frames for the current scene and
+//! hands each completed scene off to `ChunkBroker`, which farms it out to a
+//! pool of `available_parallelism()` worker threads. Each worker encodes
+//! just its scene's frames to a numbered chunk file (a fresh encoder per
+//! chunk means the first frame is always a keyframe). Once every chunk has
+//! finished, `concat_chunks` stitches them back together in scene order and
+//! muxes in the full audio track, all in-process via `ffmpeg-next` stream
+//! copying — no subprocess, so this stays consistent with the rest of the
+//! A/V pipeline.
+
+use color_eyre::{eyre, Result};
+use ffmpeg_next as ffmpeg;
+use opencv::core::{Mat, Size};
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{channel, sync_channel, Receiver, SyncSender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+use crate::encoder::VideoChunkEncoder;
+
+struct ChunkJob {
+    index: usize,
+    /// Each frame paired with its PTS (already in the chunk encoder's
+    /// thousand-ticks-per-frame time base, derived from the capture clock) so a
+    /// stalled camera frame shows up as a timing gap rather than shifting
+    /// the whole chunk.
+    frames: Vec<(Mat, i64)>,
+}
+
+struct ChunkResult {
+    index: usize,
+    path: PathBuf,
+}
+
+pub struct ChunkBroker {
+    job_tx: SyncSender<ChunkJob>,
+    result_rx: Receiver<Result<ChunkResult>>,
+    workers: Vec<JoinHandle<()>>,
+    submitted: usize,
+}
+
+impl ChunkBroker {
+    pub fn new(chunk_dir: PathBuf, frame_size: Size, fps: u64) -> Result<Self> {
+        std::fs::create_dir_all(&chunk_dir)?;
+
+        let worker_count = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        let (job_tx, job_rx) = sync_channel::<ChunkJob>(worker_count * 2);
+        let job_rx = Arc::new(Mutex::new(job_rx));
+        let (result_tx, result_rx) = channel();
+
+        let workers = (0..worker_count)
+            .map(|_| {
+                let job_rx = job_rx.clone();
+                let result_tx = result_tx.clone();
+                let chunk_dir = chunk_dir.clone();
+
+                thread::spawn(move || loop {
+                    let job = job_rx.lock().unwrap().recv();
+
+                    let Ok(job) = job else { break };
+
+                    let result = encode_chunk(&chunk_dir, frame_size, fps, job);
+                    if result_tx.send(result).is_err() {
+                        break;
+                    }
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            job_tx,
+            result_rx,
+            workers,
+            submitted: 0,
+        })
+    }
+
+    /// Hand a completed scene's frames (each tagged with its capture-clock
+    /// PTS) to the next free worker.
+    pub fn submit(&mut self, frames: Vec<(Mat, i64)>) {
+        let index = self.submitted;
+        self.submitted += 1;
+
+        let _ = self.job_tx.send(ChunkJob { index, frames });
+    }
+
+    /// Wait for every submitted chunk to finish encoding and return their
+    /// paths in scene order, ready to concatenate.
+    pub fn finish(self) -> Result<Vec<PathBuf>> {
+        drop(self.job_tx);
+
+        let mut results = Vec::with_capacity(self.submitted);
+        for _ in 0..self.submitted {
+            let result = self
+                .result_rx
+                .recv()
+                .map_err(|_| eyre::eyre!("chunk encoder worker disconnected"))??;
+            results.push(result);
+        }
+
+        for worker in self.workers {
+            let _ = worker.join();
+        }
+
+        results.sort_by_key(|r| r.index);
+        Ok(results.into_iter().map(|r| r.path).collect())
+    }
+}
+
+fn encode_chunk(chunk_dir: &Path, frame_size: Size, fps: u64, job: ChunkJob) -> Result<ChunkResult> {
+    let path = chunk_dir.join(format!("chunk_{:05}.mp4", job.index));
+    let mut encoder = VideoChunkEncoder::new(&path, (frame_size.width, frame_size.height), fps)?;
+
+    for (frame, pts) in &job.frames {
+        encoder.push_frame(frame, *pts)?;
+    }
+
+    encoder.finish()?;
+
+    Ok(ChunkResult {
+        index: job.index,
+        path,
+    })
+}
+
+/// Concatenate encoded chunks (already in index order) and mux in the full
+/// audio track, stream-copying packets straight from the chunk files and the
+/// audio file into the final container (no re-encode, no subprocess). Each
+/// chunk's video packets already carry an absolute PTS derived from the same
+/// capture clock the audio track's sample count tracks, so no cross-chunk
+/// offset needs to be added here - the two tracks line up on the real
+/// recording timeline as-is. Audio and video packets are interleaved by
+/// timestamp as they're written so the container doesn't have to buffer an
+/// entire track in memory.
+pub fn concat_chunks(chunk_paths: &[PathBuf], audio_path: &Path, final_output: &Path) -> Result<()> {
+    if chunk_paths.is_empty() {
+        return Err(eyre::eyre!("no chunks to concatenate"));
+    }
+
+    ffmpeg::init()?;
+
+    let first_chunk = ffmpeg::format::input(&chunk_paths[0])?;
+    let in_video_stream = first_chunk
+        .streams()
+        .best(ffmpeg::media::Type::Video)
+        .ok_or_else(|| eyre::eyre!("chunk {:?} has no video stream", chunk_paths[0]))?;
+    let video_time_base = in_video_stream.time_base();
+    let video_params = in_video_stream.parameters();
+    drop(first_chunk);
+
+    let mut audio_input = ffmpeg::format::input(audio_path)?;
+    let in_audio_stream = audio_input
+        .streams()
+        .best(ffmpeg::media::Type::Audio)
+        .ok_or_else(|| eyre::eyre!("{:?} has no audio stream", audio_path))?;
+    let audio_in_index = in_audio_stream.index();
+    let audio_time_base = in_audio_stream.time_base();
+    let audio_params = in_audio_stream.parameters();
+
+    let mut output = ffmpeg::format::output(final_output)?;
+    let out_video_index = output
+        .add_stream(ffmpeg::encoder::find(ffmpeg::codec::Id::None))?
+        .index();
+    output.stream_mut(out_video_index).unwrap().set_parameters(video_params);
+    let out_audio_index = output
+        .add_stream(ffmpeg::encoder::find(ffmpeg::codec::Id::None))?
+        .index();
+    output.stream_mut(out_audio_index).unwrap().set_parameters(audio_params);
+
+    output.write_header()?;
+
+    let out_video_time_base = output.stream(out_video_index).unwrap().time_base();
+    let out_audio_time_base = output.stream(out_audio_index).unwrap().time_base();
+
+    let mut audio_packets = audio_input.packets().peekable();
+
+    for chunk_path in chunk_paths {
+        let mut chunk_input = ffmpeg::format::input(chunk_path)?;
+        let video_in_index = chunk_input
+            .streams()
+            .best(ffmpeg::media::Type::Video)
+            .ok_or_else(|| eyre::eyre!("chunk {:?} has no video stream", chunk_path))?
+            .index();
+
+        for (stream, mut packet) in chunk_input.packets() {
+            if stream.index() != video_in_index {
+                continue;
+            }
+
+            // drain any audio packets that sort before this video packet so the
+            // two tracks stay interleaved by time rather than writing one whole
+            // track before the other.
+            let video_time = packet.pts().unwrap_or(0) as f64 * f64::from(video_time_base);
+            while let Some((audio_stream, next_audio)) = audio_packets.peek() {
+                if audio_stream.index() != audio_in_index {
+                    audio_packets.next();
+                    continue;
+                }
+
+                let audio_time = next_audio.pts().unwrap_or(0) as f64 * f64::from(audio_time_base);
+                if audio_time > video_time {
+                    break;
+                }
+
+                let (_, mut audio_packet) = audio_packets.next().unwrap();
+                audio_packet.rescale_ts(audio_time_base, out_audio_time_base);
+                audio_packet.set_stream(out_audio_index);
+                audio_packet.write_interleaved(&mut output)?;
+            }
+
+            packet.rescale_ts(video_time_base, out_video_time_base);
+            packet.set_stream(out_video_index);
+            packet.write_interleaved(&mut output)?;
+        }
+    }
+
+    // the video track has ended; flush whatever audio is left.
+    for (stream, mut audio_packet) in audio_packets {
+        if stream.index() != audio_in_index {
+            continue;
+        }
+
+        audio_packet.rescale_ts(audio_time_base, out_audio_time_base);
+        audio_packet.set_stream(out_audio_index);
+        audio_packet.write_interleaved(&mut output)?;
+    }
+
+    output.write_trailer()?;
+
+    for path in chunk_paths {
+        std::fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,195 @@
+//! Persistent recording configuration, loaded from `recording.yml` in the
+//! user's config directory (`~/.config/webcam_art` on Linux). If the file is
+//! missing, `load` writes out `from_default()` and uses that, so the very
+//! first run leaves behind an editable copy instead of failing or silently
+//! using constants nobody can find.
+
+use color_eyre::{eyre, Result};
+use opencv::core::Size;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const DEFAULT_ASCII_RAMP: &str = " .:-=+*#%@";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingConfig {
+    pub capture_width: i32,
+    pub capture_height: i32,
+    pub target_fps: u64,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub ascii_ramp: String,
+    pub font_scale: f64,
+    pub output_dir: PathBuf,
+    /// Overlay directional edge glyphs (`|`, `-`, `/`, `\`) over cells whose
+    /// Sobel gradient magnitude is strong. The glyph is picked from the
+    /// gradient angle alone; how clearly oriented the gradient is is not
+    /// checked.
+    #[serde(default)]
+    pub edge_overlay: bool,
+    /// Sample the source BGR pixel per cell and render it with its true
+    /// color instead of the default plain grayscale ramp.
+    #[serde(default)]
+    pub truecolor: bool,
+    /// Normalized sum-of-absolute-differences threshold between a frame's
+    /// 32x32 luma thumbnail and the previous one before `SceneDetector`
+    /// considers it a scene cut.
+    #[serde(default = "default_scene_change_threshold")]
+    pub scene_change_threshold: f64,
+    /// Minimum number of frames a scene must run for before another cut is
+    /// considered, so a hard threshold doesn't chop every noisy frame into
+    /// its own scene.
+    #[serde(default = "default_min_scene_len")]
+    pub min_scene_len: usize,
+}
+
+fn default_scene_change_threshold() -> f64 {
+    0.15
+}
+
+fn default_min_scene_len() -> usize {
+    12
+}
+
+impl RecordingConfig {
+    fn from_default() -> Self {
+        Self {
+            capture_width: 640,
+            capture_height: 480,
+            target_fps: 30,
+            sample_rate: 48000,
+            channels: 1,
+            ascii_ramp: DEFAULT_ASCII_RAMP.to_string(),
+            font_scale: 0.4,
+            output_dir: PathBuf::from("."),
+            edge_overlay: false,
+            truecolor: false,
+            scene_change_threshold: default_scene_change_threshold(),
+            min_scene_len: default_min_scene_len(),
+        }
+    }
+
+    fn config_path() -> Result<PathBuf> {
+        let dir = dirs::config_dir()
+            .ok_or_else(|| eyre::eyre!("no config directory available on this platform"))?
+            .join("webcam_art");
+
+        Ok(dir.join("recording.yml"))
+    }
+
+    /// Load the config file, falling back to written-out defaults when it's
+    /// missing.
+    pub fn load() -> Result<Self> {
+        let path = Self::config_path()?;
+
+        if !path.exists() {
+            let config = Self::from_default();
+            config.save()?;
+
+            return Ok(config);
+        }
+
+        let contents = std::fs::read_to_string(&path)?;
+        let config: Self = serde_yaml::from_str(&contents)?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Rejects settings that would otherwise panic deep in the capture loop
+    /// (e.g. a divide-by-zero on `target_fps`) instead of failing fast here
+    /// with a message that actually points at the config file.
+    fn validate(&self) -> Result<()> {
+        if self.target_fps == 0 {
+            return Err(eyre::eyre!("target_fps must be greater than zero"));
+        }
+
+        if self.sample_rate == 0 {
+            return Err(eyre::eyre!("sample_rate must be greater than zero"));
+        }
+
+        if self.capture_width <= 0 || self.capture_height <= 0 {
+            return Err(eyre::eyre!(
+                "capture_width and capture_height must be greater than zero"
+            ));
+        }
+
+        if self.scene_change_threshold <= 0.0 {
+            return Err(eyre::eyre!("scene_change_threshold must be greater than zero"));
+        }
+
+        Ok(())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::config_path()?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(&path, serde_yaml::to_string(self)?)?;
+
+        Ok(())
+    }
+
+    pub fn frame_size(&self) -> Size {
+        Size::new(self.capture_width, self.capture_height)
+    }
+
+    /// Chars of the configured ramp, falling back to the built-in default
+    /// if a hand-edited config left it empty (an empty ramp has no levels
+    /// to map a pixel onto).
+    pub fn ascii_ramp_chars(&self) -> Vec<char> {
+        if self.ascii_ramp.is_empty() {
+            return DEFAULT_ASCII_RAMP.chars().collect();
+        }
+
+        self.ascii_ramp.chars().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_accepts_defaults() {
+        assert!(RecordingConfig::from_default().validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_zero_fps() {
+        let mut config = RecordingConfig::from_default();
+        config.target_fps = 0;
+
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_zero_sample_rate() {
+        let mut config = RecordingConfig::from_default();
+        config.sample_rate = 0;
+
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_non_positive_capture_dimensions() {
+        let mut width = RecordingConfig::from_default();
+        width.capture_width = 0;
+        assert!(width.validate().is_err());
+
+        let mut height = RecordingConfig::from_default();
+        height.capture_height = -1;
+        assert!(height.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_non_positive_scene_change_threshold() {
+        let mut config = RecordingConfig::from_default();
+        config.scene_change_threshold = 0.0;
+
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,546 @@
+//! In-process A/V muxing built on `ffmpeg-next`.
+//!
+//! `AvMuxer` owns a video and an audio stream on a single output container and
+//! is responsible for converting raw frames/samples into the encoders'
+//! expected formats and for stamping presentation timestamps so the muxer can
+//! interleave packets by DTS. Video PTS is derived from a monotonic capture
+//! clock (elapsed wall-clock time since the first frame); audio PTS is
+//! derived from a running count of samples already pushed. Because both
+//! clocks are independent of how often `push_video_frame`/`push_audio_samples`
+//! are actually called, a stalled camera frame no longer drags the audio
+//! track out of sync with it.
+
+use color_eyre::{eyre, Result};
+use ffmpeg_next as ffmpeg;
+use opencv::core::{Mat, MatTraitConst};
+use std::path::Path;
+use std::time::Instant;
+
+/// The video time base shared by `AvMuxer` and `VideoChunkEncoder`:
+/// 1/(fps * 1000), a thousand ticks per frame interval rather than one, so a PTS
+/// derived from elapsed wall-clock time (`elapsed_secs * video_pts_scale(fps)`)
+/// can be stamped directly without first rounding to a frame count.
+pub fn video_pts_scale(fps: u64) -> f64 {
+    fps as f64 * 1000.0
+}
+
+fn video_time_base(fps: u64) -> ffmpeg::Rational {
+    ffmpeg::Rational::new(1, fps as i32 * 1000)
+}
+
+/// Copy a tightly-packed BGR24 buffer (as returned by OpenCV's `Mat`) into an
+/// ffmpeg frame plane row by row. `ffmpeg_next::util::frame::Video::new`
+/// allocates its plane via `av_frame_get_buffer`, which pads each row's
+/// `linesize`/stride up to a 32-byte boundary, so a single flat copy is only
+/// correct when `width * 3` already happens to be a multiple of 32 — any
+/// other resolution lands every row after the first at the wrong offset.
+fn copy_bgr24_into(frame: &mut ffmpeg::util::frame::Video, data: &[u8], width: usize, height: usize) {
+    let stride = frame.stride(0);
+    let row_bytes = width * 3;
+    let dst = frame.data_mut(0);
+
+    for y in 0..height {
+        dst[y * stride..y * stride + row_bytes]
+            .copy_from_slice(&data[y * row_bytes..(y + 1) * row_bytes]);
+    }
+}
+
+/// Buffers incoming audio samples and hands `encode` exactly one encoder
+/// frame at a time, since fixed-frame-size codecs (Opus, AAC) reject
+/// anything that isn't precisely `frame_size` samples and the caller's
+/// capture chunk size is never trusted to already match it. Shared by
+/// `AvMuxer` and `AudioMuxer` (the audio-only sibling of
+/// `VideoChunkEncoder`), so this buffering/padding logic isn't copy-pasted
+/// into each of them.
+#[derive(Default)]
+struct SampleBuffer {
+    pending: Vec<f32>,
+}
+
+impl SampleBuffer {
+    /// Push samples and call `encode` once per complete `frame_size`-sized
+    /// frame. `frame_size == 0` means the encoder accepts any length, so the
+    /// whole buffer is flushed through `encode` at once.
+    fn push(
+        &mut self,
+        samples: &[f32],
+        frame_size: usize,
+        mut encode: impl FnMut(&[f32]) -> Result<()>,
+    ) -> Result<()> {
+        self.pending.extend_from_slice(samples);
+
+        if frame_size == 0 {
+            let frame = std::mem::take(&mut self.pending);
+            return encode(&frame);
+        }
+
+        while self.pending.len() >= frame_size {
+            let frame: Vec<f32> = self.pending.drain(..frame_size).collect();
+            encode(&frame)?;
+        }
+
+        Ok(())
+    }
+
+    /// Pad whatever's left with silence to a full frame (fixed-size codecs
+    /// reject a short final frame) and encode it.
+    fn flush(&mut self, frame_size: usize, encode: impl FnOnce(&[f32]) -> Result<()>) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut last_frame = std::mem::take(&mut self.pending);
+        if frame_size > 0 {
+            last_frame.resize(frame_size, 0.0);
+        }
+
+        encode(&last_frame)
+    }
+}
+
+/// Drains every packet `receive_packet` currently has ready, stamping its
+/// stream index and rescaling its timestamps before writing it out
+/// interleaved. Shared by every encoder/muxer in this module - each just
+/// supplies its own `receive_packet` as a closure, since `Video` and `Audio`
+/// encoders don't share a public trait for it.
+fn drain_packets(
+    mut receive_packet: impl FnMut(&mut ffmpeg::Packet) -> std::result::Result<(), ffmpeg::Error>,
+    stream_index: usize,
+    in_time_base: ffmpeg::Rational,
+    output: &mut ffmpeg::format::context::Output,
+) -> Result<()> {
+    let mut packet = ffmpeg::Packet::empty();
+
+    loop {
+        match receive_packet(&mut packet) {
+            Ok(()) => {
+                packet.set_stream(stream_index);
+                packet.rescale_ts(in_time_base, output.stream(stream_index).unwrap().time_base());
+                packet.write_interleaved(output)?;
+            }
+            Err(ffmpeg::Error::Other { errno }) if errno == ffmpeg::util::error::EAGAIN => break,
+            Err(ffmpeg::Error::Eof) => break,
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(())
+}
+
+pub struct AvMuxer {
+    output: ffmpeg::format::context::Output,
+    video_stream_index: usize,
+    audio_stream_index: usize,
+    video_encoder: ffmpeg::codec::encoder::Video,
+    audio_encoder: ffmpeg::codec::encoder::Audio,
+    scaler: ffmpeg::software::scaling::Context,
+    resampler: ffmpeg::software::resampling::Context,
+    video_time_base: ffmpeg::Rational,
+    audio_time_base: ffmpeg::Rational,
+    capture_start: Option<Instant>,
+    samples_written: i64,
+    pending: SampleBuffer,
+}
+
+impl AvMuxer {
+    /// Open the same A/V pipeline against a network URL instead of a local
+    /// file, using the given container format (e.g. `"rtsp"`) so the
+    /// streaming path can reuse every bit of encode/PTS logic below.
+    pub fn new_streaming(
+        url: &str,
+        format_name: &str,
+        frame_size: (i32, i32),
+        fps: u64,
+        sample_rate: u32,
+        channels: u16,
+    ) -> Result<Self> {
+        let output = ffmpeg::format::output_as(url, format_name)?;
+        Self::from_output(output, frame_size, fps, sample_rate, channels)
+    }
+
+    fn from_output(
+        mut output: ffmpeg::format::context::Output,
+        frame_size: (i32, i32),
+        fps: u64,
+        sample_rate: u32,
+        channels: u16,
+    ) -> Result<Self> {
+        ffmpeg::init()?;
+
+        let video_codec = ffmpeg::encoder::find(ffmpeg::codec::Id::H264)
+            .ok_or_else(|| eyre::eyre!("no H264 encoder available"))?;
+        let mut video_stream = output.add_stream(video_codec)?;
+        let video_time_base = video_time_base(fps);
+        let mut video_encoder =
+            ffmpeg::codec::context::Context::new_with_codec(video_codec).encoder().video()?;
+        video_encoder.set_width(frame_size.0 as u32);
+        video_encoder.set_height(frame_size.1 as u32);
+        video_encoder.set_format(ffmpeg::format::Pixel::YUV420P);
+        video_encoder.set_time_base(video_time_base);
+        video_encoder.set_frame_rate(Some((fps as i32, 1)));
+        let video_encoder = video_encoder.open_as(video_codec)?;
+        video_stream.set_time_base(video_time_base);
+        video_stream.set_parameters(&video_encoder);
+        let video_stream_index = video_stream.index();
+
+        let audio_codec = ffmpeg::encoder::find(ffmpeg::codec::Id::OPUS)
+            .ok_or_else(|| eyre::eyre!("no Opus encoder available"))?;
+        let mut audio_stream = output.add_stream(audio_codec)?;
+        let audio_time_base = ffmpeg::Rational::new(1, sample_rate as i32);
+        let mut audio_encoder =
+            ffmpeg::codec::context::Context::new_with_codec(audio_codec).encoder().audio()?;
+        audio_encoder.set_rate(sample_rate as i32);
+        audio_encoder.set_channel_layout(if channels == 1 {
+            ffmpeg::channel_layout::ChannelLayout::MONO
+        } else {
+            ffmpeg::channel_layout::ChannelLayout::STEREO
+        });
+        audio_encoder.set_format(ffmpeg::format::Sample::F32(
+            ffmpeg::format::sample::Type::Packed,
+        ));
+        audio_encoder.set_time_base(audio_time_base);
+        let audio_encoder = audio_encoder.open_as(audio_codec)?;
+        audio_stream.set_time_base(audio_time_base);
+        audio_stream.set_parameters(&audio_encoder);
+        let audio_stream_index = audio_stream.index();
+
+        let scaler = ffmpeg::software::scaling::Context::get(
+            ffmpeg::format::Pixel::BGR24,
+            frame_size.0 as u32,
+            frame_size.1 as u32,
+            ffmpeg::format::Pixel::YUV420P,
+            frame_size.0 as u32,
+            frame_size.1 as u32,
+            ffmpeg::software::scaling::Flags::BILINEAR,
+        )?;
+
+        let resampler = ffmpeg::software::resampling::Context::get(
+            ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Packed),
+            audio_encoder.channel_layout(),
+            sample_rate,
+            audio_encoder.format(),
+            audio_encoder.channel_layout(),
+            sample_rate,
+        )?;
+
+        output.write_header()?;
+
+        Ok(Self {
+            output,
+            video_stream_index,
+            audio_stream_index,
+            video_encoder,
+            audio_encoder,
+            scaler,
+            resampler,
+            video_time_base,
+            audio_time_base,
+            capture_start: None,
+            samples_written: 0,
+            pending: SampleBuffer::default(),
+        })
+    }
+
+    /// Encode and mux one BGR frame, stamping its PTS from elapsed capture
+    /// time rather than frame count, so dropped/stalled frames don't shift
+    /// later frames off their real-world timing.
+    pub fn push_video_frame(&mut self, frame: &Mat) -> Result<()> {
+        let capture_start = *self.capture_start.get_or_insert_with(Instant::now);
+        let elapsed = capture_start.elapsed();
+        let pts = (elapsed.as_secs_f64() * self.video_time_base.denominator() as f64
+            / self.video_time_base.numerator() as f64) as i64;
+
+        let width = frame.cols() as u32;
+        let height = frame.rows() as u32;
+        let data = frame.data_bytes()?;
+
+        let mut src = ffmpeg::util::frame::Video::new(ffmpeg::format::Pixel::BGR24, width, height);
+        copy_bgr24_into(&mut src, data, width as usize, height as usize);
+
+        let mut dst = ffmpeg::util::frame::Video::empty();
+        self.scaler.run(&src, &mut dst)?;
+        dst.set_pts(Some(pts));
+
+        self.video_encoder.send_frame(&dst)?;
+        self.drain_encoder(false)
+    }
+
+    /// Buffer incoming samples and encode+mux exactly one encoder frame at a
+    /// time; the codec (Opus here) rejects any frame that isn't exactly
+    /// `frame_size()` samples, so the caller's chunk size is never trusted
+    /// to already match it.
+    pub fn push_audio_samples(&mut self, samples: &[f32]) -> Result<()> {
+        let frame_size = self.audio_encoder.frame_size() as usize;
+        let mut pending = std::mem::take(&mut self.pending);
+        let result = pending.push(samples, frame_size, |frame| self.encode_audio_frame(frame));
+        self.pending = pending;
+        result
+    }
+
+    fn encode_audio_frame(&mut self, samples: &[f32]) -> Result<()> {
+        let mut src = ffmpeg::util::frame::Audio::new(
+            ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Packed),
+            samples.len(),
+            self.audio_encoder.channel_layout(),
+        );
+        src.plane_mut::<f32>(0).copy_from_slice(samples);
+        src.set_rate(self.audio_encoder.rate());
+
+        let mut dst = ffmpeg::util::frame::Audio::empty();
+        self.resampler.run(&src, &mut dst)?;
+        dst.set_pts(Some(self.samples_written));
+        self.samples_written += dst.samples() as i64;
+
+        self.audio_encoder.send_frame(&dst)?;
+        self.drain_encoder(true)
+    }
+
+    fn drain_encoder(&mut self, audio: bool) -> Result<()> {
+        if audio {
+            drain_packets(
+                |p| self.audio_encoder.receive_packet(p),
+                self.audio_stream_index,
+                self.audio_time_base,
+                &mut self.output,
+            )
+        } else {
+            drain_packets(
+                |p| self.video_encoder.receive_packet(p),
+                self.video_stream_index,
+                self.video_time_base,
+                &mut self.output,
+            )
+        }
+    }
+
+    /// Flush both encoders and write the trailer. Consumes `self` so a
+    /// finished muxer can't accidentally be written to again.
+    pub fn finish(mut self) -> Result<()> {
+        let frame_size = self.audio_encoder.frame_size() as usize;
+        let mut pending = std::mem::take(&mut self.pending);
+        let flush_result = pending.flush(frame_size, |frame| self.encode_audio_frame(frame));
+        self.pending = pending;
+        flush_result?;
+
+        self.video_encoder.send_eof()?;
+        self.drain_encoder(false)?;
+
+        self.audio_encoder.send_eof()?;
+        self.drain_encoder(true)?;
+
+        self.output.write_trailer()?;
+
+        Ok(())
+    }
+}
+
+/// A single-stream video encoder used by the chunked recording pipeline to
+/// encode one scene's worth of frames into its own small file. A fresh
+/// encoder is opened per chunk, so its first frame is always a keyframe —
+/// that's what makes a scene boundary a forced keyframe with no extra
+/// bookkeeping. Shares `AvMuxer`'s thousand-ticks-per-frame time base so a PTS
+/// derived from the same capture clock drops straight in without
+/// conversion, which is what lets `concat_chunks` stitch chunks back
+/// together on the real capture timeline instead of an assumed constant
+/// frame interval.
+pub struct VideoChunkEncoder {
+    output: ffmpeg::format::context::Output,
+    stream_index: usize,
+    encoder: ffmpeg::codec::encoder::Video,
+    scaler: ffmpeg::software::scaling::Context,
+    time_base: ffmpeg::Rational,
+}
+
+impl VideoChunkEncoder {
+    pub fn new(path: &Path, frame_size: (i32, i32), fps: u64) -> Result<Self> {
+        ffmpeg::init()?;
+
+        let mut output = ffmpeg::format::output(path)?;
+
+        let codec = ffmpeg::encoder::find(ffmpeg::codec::Id::H264)
+            .ok_or_else(|| eyre::eyre!("no H264 encoder available"))?;
+        let mut stream = output.add_stream(codec)?;
+        let time_base = video_time_base(fps);
+        let mut encoder =
+            ffmpeg::codec::context::Context::new_with_codec(codec).encoder().video()?;
+        encoder.set_width(frame_size.0 as u32);
+        encoder.set_height(frame_size.1 as u32);
+        encoder.set_format(ffmpeg::format::Pixel::YUV420P);
+        encoder.set_time_base(time_base);
+        encoder.set_frame_rate(Some((fps as i32, 1)));
+        let encoder = encoder.open_as(codec)?;
+        stream.set_time_base(time_base);
+        stream.set_parameters(&encoder);
+        let stream_index = stream.index();
+
+        let scaler = ffmpeg::software::scaling::Context::get(
+            ffmpeg::format::Pixel::BGR24,
+            frame_size.0 as u32,
+            frame_size.1 as u32,
+            ffmpeg::format::Pixel::YUV420P,
+            frame_size.0 as u32,
+            frame_size.1 as u32,
+            ffmpeg::software::scaling::Flags::BILINEAR,
+        )?;
+
+        output.write_header()?;
+
+        Ok(Self {
+            output,
+            stream_index,
+            encoder,
+            scaler,
+            time_base,
+        })
+    }
+
+    /// Encode one BGR frame, stamping it with `pts` (already expressed in
+    /// this encoder's time base) rather than a bare frame counter, so a
+    /// stalled capture frame shows up as a timing gap instead of silently
+    /// shifting every later frame in this chunk.
+    pub fn push_frame(&mut self, frame: &Mat, pts: i64) -> Result<()> {
+        let width = frame.cols() as u32;
+        let height = frame.rows() as u32;
+        let data = frame.data_bytes()?;
+
+        let mut src = ffmpeg::util::frame::Video::new(ffmpeg::format::Pixel::BGR24, width, height);
+        copy_bgr24_into(&mut src, data, width as usize, height as usize);
+
+        let mut dst = ffmpeg::util::frame::Video::empty();
+        self.scaler.run(&src, &mut dst)?;
+        dst.set_pts(Some(pts));
+
+        self.encoder.send_frame(&dst)?;
+        self.drain()
+    }
+
+    fn drain(&mut self) -> Result<()> {
+        drain_packets(
+            |p| self.encoder.receive_packet(p),
+            self.stream_index,
+            self.time_base,
+            &mut self.output,
+        )
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        self.encoder.send_eof()?;
+        self.drain()?;
+        self.output.write_trailer()?;
+
+        Ok(())
+    }
+}
+
+/// An audio-only muxer, used to capture the full recording's mic audio on
+/// its own timeline while video is buffered and encoded in scene chunks.
+/// The final chunked file is produced by muxing this track back in once
+/// every chunk has finished encoding (see `chunked::concat_chunks`).
+pub struct AudioMuxer {
+    output: ffmpeg::format::context::Output,
+    stream_index: usize,
+    encoder: ffmpeg::codec::encoder::Audio,
+    resampler: ffmpeg::software::resampling::Context,
+    time_base: ffmpeg::Rational,
+    samples_written: i64,
+    pending: SampleBuffer,
+}
+
+impl AudioMuxer {
+    pub fn new(path: &Path, sample_rate: u32, channels: u16) -> Result<Self> {
+        ffmpeg::init()?;
+
+        let mut output = ffmpeg::format::output(path)?;
+
+        let codec = ffmpeg::encoder::find(ffmpeg::codec::Id::AAC)
+            .ok_or_else(|| eyre::eyre!("no AAC encoder available"))?;
+        let mut stream = output.add_stream(codec)?;
+        let time_base = ffmpeg::Rational::new(1, sample_rate as i32);
+        let mut encoder =
+            ffmpeg::codec::context::Context::new_with_codec(codec).encoder().audio()?;
+        encoder.set_rate(sample_rate as i32);
+        encoder.set_channel_layout(if channels == 1 {
+            ffmpeg::channel_layout::ChannelLayout::MONO
+        } else {
+            ffmpeg::channel_layout::ChannelLayout::STEREO
+        });
+        encoder.set_format(ffmpeg::format::Sample::F32(
+            ffmpeg::format::sample::Type::Packed,
+        ));
+        encoder.set_time_base(time_base);
+        let encoder = encoder.open_as(codec)?;
+        stream.set_time_base(time_base);
+        stream.set_parameters(&encoder);
+        let stream_index = stream.index();
+
+        let resampler = ffmpeg::software::resampling::Context::get(
+            ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Packed),
+            encoder.channel_layout(),
+            sample_rate,
+            encoder.format(),
+            encoder.channel_layout(),
+            sample_rate,
+        )?;
+
+        output.write_header()?;
+
+        Ok(Self {
+            output,
+            stream_index,
+            encoder,
+            resampler,
+            time_base,
+            samples_written: 0,
+            pending: SampleBuffer::default(),
+        })
+    }
+
+    pub fn push_samples(&mut self, samples: &[f32]) -> Result<()> {
+        let frame_size = self.encoder.frame_size() as usize;
+        let mut pending = std::mem::take(&mut self.pending);
+        let result = pending.push(samples, frame_size, |frame| self.encode_frame(frame));
+        self.pending = pending;
+        result
+    }
+
+    fn encode_frame(&mut self, samples: &[f32]) -> Result<()> {
+        let mut src = ffmpeg::util::frame::Audio::new(
+            ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Packed),
+            samples.len(),
+            self.encoder.channel_layout(),
+        );
+        src.plane_mut::<f32>(0).copy_from_slice(samples);
+        src.set_rate(self.encoder.rate());
+
+        let mut dst = ffmpeg::util::frame::Audio::empty();
+        self.resampler.run(&src, &mut dst)?;
+        dst.set_pts(Some(self.samples_written));
+        self.samples_written += dst.samples() as i64;
+
+        self.encoder.send_frame(&dst)?;
+        self.drain()
+    }
+
+    fn drain(&mut self) -> Result<()> {
+        drain_packets(
+            |p| self.encoder.receive_packet(p),
+            self.stream_index,
+            self.time_base,
+            &mut self.output,
+        )
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        let frame_size = self.encoder.frame_size() as usize;
+        let mut pending = std::mem::take(&mut self.pending);
+        let flush_result = pending.flush(frame_size, |frame| self.encode_frame(frame));
+        self.pending = pending;
+        flush_result?;
+
+        self.encoder.send_eof()?;
+        self.drain()?;
+        self.output.write_trailer()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,625 @@
+mod ascii;
+mod chunked;
+mod config;
+mod encoder;
+mod scene;
+mod stream;
+
+use color_eyre::{eyre, Result};
+use env_logger::Builder;
+use log::LevelFilter;
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use parking_lot::Mutex;
+use ringbuf::HeapRb;
+
+use crossterm::{
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use opencv::{
+    core::Size,
+    imgproc,
+    prelude::*,
+    videoio::{VideoCapture, CAP_ANY},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Style},
+    widgets::{Block, Borders, Paragraph},
+    Terminal,
+};
+
+use std::{
+    env,
+    fs::File,
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use ascii::AsciiFrame;
+use chunked::ChunkBroker;
+use config::RecordingConfig;
+use encoder::{video_pts_scale, AudioMuxer};
+use scene::SceneDetector;
+use stream::{sign_stream_token, ConnectionState, StreamPublisher};
+
+const STREAM_TOKEN_TTL_SECS: u64 = 6 * 60 * 60;
+// Reconnect backoff for the streaming publish path: doubles after each
+// failed attempt (500ms, 1s, 2s, 4s, 8s) before giving up and surfacing
+// `ConnectionState::Disconnected` instead of spinning forever.
+const RECONNECT_MAX_ATTEMPTS: u32 = 5;
+const RECONNECT_BASE_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AppMode {
+    Idle,
+    Recording,
+    Streaming,
+}
+
+struct App {
+    ascii_frame: AsciiFrame,
+    fps: f64,
+    mode: AppMode,
+    stream_state: Option<ConnectionState>,
+    ascii_ramp: Vec<char>,
+    edge_overlay: bool,
+    truecolor: bool,
+}
+
+impl App {
+    fn new(config: &RecordingConfig) -> Self {
+        App {
+            ascii_frame: AsciiFrame::default(),
+            fps: 0.0,
+            mode: AppMode::Idle,
+            stream_state: None,
+            ascii_ramp: config.ascii_ramp_chars(),
+            edge_overlay: config.edge_overlay,
+            truecolor: config.truecolor,
+        }
+    }
+
+    fn update(&mut self, frame: &Mat) -> opencv::Result<()> {
+        self.ascii_frame = ascii::process_frame(frame, &self.ascii_ramp, self.edge_overlay, self.truecolor)?;
+
+        Ok(())
+    }
+}
+
+fn setup_logging() -> Result<(), Box<dyn std::error::Error>> {
+    let log_file = File::create("output.log")?;
+
+    Builder::new()
+        .filter(None, LevelFilter::Info)
+        .format(|buf, record| writeln!(buf, "{}: {}", record.level(), record.args()))
+        .target(env_logger::Target::Pipe(Box::new(log_file)))
+        .init();
+
+    Ok(())
+}
+
+fn start_recording(
+    camera: Arc<Mutex<VideoCapture>>,
+    is_recording: Arc<AtomicBool>,
+    config: RecordingConfig,
+) -> Result<()> {
+    let final_output = config.output_dir.join("final_output.mp4");
+    let audio_output = config.output_dir.join("output_audio.m4a");
+    let chunk_dir = config.output_dir.join(".chunks");
+    let frame_size = config.frame_size();
+    let ascii_ramp = config.ascii_ramp_chars();
+
+    // audio is cheap to encode in real time, so it streams straight to its
+    // own track; video is buffered per-scene and farmed out to the chunk
+    // broker below so encode throughput scales with core count.
+    let mut audio_muxer = AudioMuxer::new(&audio_output, config.sample_rate, config.channels)?;
+    let mut broker = ChunkBroker::new(chunk_dir.clone(), frame_size, config.target_fps)?;
+    let mut scene_detector = SceneDetector::new(config.scene_change_threshold, config.min_scene_len);
+    let mut scene_frames: Vec<(Mat, i64)> = Vec::new();
+    // Reflects real capture time rather than an assumed constant frame
+    // interval - the same fix chunk0-1 made for `AvMuxer`. `video_pts_scale`
+    // is the same thousand-ticks-per-frame factor `VideoChunkEncoder`'s time base
+    // uses, so a PTS derived from this clock drops straight into a chunk
+    // without further conversion.
+    let video_pts_scale = video_pts_scale(config.target_fps);
+
+    // initialize audio recording
+    let host = cpal::default_host();
+    let device = host
+        .default_input_device()
+        .ok_or_else(|| eyre::eyre!("no input device available"))?;
+    let stream_config = cpal::StreamConfig {
+        channels: config.channels,
+        sample_rate: cpal::SampleRate(config.sample_rate),
+        buffer_size: cpal::BufferSize::Default,
+    };
+
+    let ring_buffer = HeapRb::<f32>::new(config.sample_rate as usize * config.channels as usize);
+    let (mut producer, mut consumer) = ring_buffer.split();
+
+    let stream = device.build_input_stream(
+        &stream_config,
+        move |data: &[f32], _: &cpal::InputCallbackInfo| {
+            producer.push_slice(data);
+        },
+        |err| log::info!("an error occurred on the audio input stream: {}", err),
+        None,
+    )?;
+
+    stream.play()?;
+
+    // Started right after the audio stream so video PTS 0 and the first
+    // audio sample pushed correspond to (as close as we can get) the same
+    // real-world instant - otherwise the two tracks start from different
+    // epochs and drift apart by that fixed gap for the whole recording.
+    let capture_start = Instant::now();
+
+    let mut frame_buffer = vec![0.0f32; config.sample_rate as usize / 50]; // 20ms frame
+
+    while is_recording.load(Ordering::Relaxed) {
+        let mut frame = Mat::default();
+        {
+            let mut camera = camera.lock();
+            camera.read(&mut frame)?;
+        }
+        let pts = (capture_start.elapsed().as_secs_f64() * video_pts_scale) as i64;
+
+        if !frame.empty() {
+            // the recorded video is always white-on-black, so there's no
+            // point sampling truecolor here even if it's on for the live
+            // terminal preview.
+            let ascii_frame = ascii::process_frame(&frame, &ascii_ramp, config.edge_overlay, false)?;
+            let ascii_image =
+                ascii::render_ascii_frame(&ascii_frame.plain_text(), frame_size, config.font_scale)?;
+
+            if scene_detector.is_boundary(&ascii_image)? && !scene_frames.is_empty() {
+                broker.submit(std::mem::take(&mut scene_frames));
+            }
+
+            scene_frames.push((ascii_image, pts));
+        }
+
+        // push whatever audio has accumulated since the last pass; the
+        // muxer stamps its own PTS from the running sample count, so it
+        // doesn't matter whether this drains every 20ms frame or falls
+        // behind for a beat.
+        while consumer.len() >= frame_buffer.len() {
+            consumer.pop_slice(&mut frame_buffer);
+            audio_muxer.push_samples(&frame_buffer)?;
+        }
+
+        thread::sleep(Duration::from_millis(1000 / config.target_fps));
+    }
+
+    // stop audio recording
+    drop(stream);
+
+    if !scene_frames.is_empty() {
+        broker.submit(scene_frames);
+    }
+
+    audio_muxer.finish()?;
+    let chunk_paths = broker.finish()?;
+    chunked::concat_chunks(&chunk_paths, &audio_output, &final_output)?;
+
+    std::fs::remove_file(&audio_output)?;
+    std::fs::remove_dir(&chunk_dir).ok();
+
+    Ok(())
+}
+
+/// Signs a fresh token and dials a new `StreamPublisher`, the same way for
+/// both the initial connect and any later reconnect attempt.
+fn connect_publisher(url: &str, frame_size: Size, config: &RecordingConfig) -> Result<StreamPublisher> {
+    let api_key = env::var("STREAM_API_KEY").unwrap_or_default();
+    let api_secret = env::var("STREAM_API_SECRET").unwrap_or_default();
+    let room = env::var("STREAM_ROOM").unwrap_or_else(|_| "webcam-art".to_string());
+    let identity = env::var("STREAM_IDENTITY").unwrap_or_else(|_| "webcam-art-publisher".to_string());
+    let expires_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        + STREAM_TOKEN_TTL_SECS;
+    let token = sign_stream_token(&api_key, &api_secret, &room, &identity, expires_at)?;
+
+    StreamPublisher::connect(
+        url,
+        &token,
+        frame_size,
+        config.target_fps,
+        config.sample_rate,
+        config.channels,
+    )
+}
+
+/// Replaces a dead `publisher` with a newly-connected one, retrying with
+/// exponential backoff. Returns `false` (and leaves `connection_state` as
+/// `Disconnected`) once `RECONNECT_MAX_ATTEMPTS` is exhausted, so the caller
+/// can stop the streaming thread instead of spinning on a connection that's
+/// never coming back.
+fn reconnect_publisher(
+    publisher: &mut StreamPublisher,
+    url: &str,
+    frame_size: Size,
+    config: &RecordingConfig,
+    connection_state: &Arc<Mutex<ConnectionState>>,
+) -> bool {
+    *connection_state.lock() = ConnectionState::Reconnecting;
+
+    for attempt in 0..RECONNECT_MAX_ATTEMPTS {
+        thread::sleep(RECONNECT_BASE_DELAY * 2u32.pow(attempt));
+
+        match connect_publisher(url, frame_size, config) {
+            Ok(fresh) => {
+                *publisher = fresh;
+                *connection_state.lock() = publisher.state;
+                return true;
+            }
+            Err(e) => log::info!("reconnect attempt {} failed: {:?}", attempt + 1, e),
+        }
+    }
+
+    *connection_state.lock() = ConnectionState::Disconnected;
+    false
+}
+
+/// Publishes the same ASCII render + mic audio pipeline used for local
+/// recording to a remote media server instead of writing to disk.
+fn start_streaming(
+    camera: Arc<Mutex<VideoCapture>>,
+    is_streaming: Arc<AtomicBool>,
+    url: String,
+    connection_state: Arc<Mutex<ConnectionState>>,
+    config: RecordingConfig,
+) -> Result<()> {
+    let frame_size = config.frame_size();
+    let ascii_ramp = config.ascii_ramp_chars();
+
+    *connection_state.lock() = ConnectionState::Connecting;
+
+    let mut publisher = connect_publisher(&url, frame_size, &config)?;
+    *connection_state.lock() = publisher.state;
+
+    // initialize audio recording
+    let host = cpal::default_host();
+    let device = host
+        .default_input_device()
+        .ok_or_else(|| eyre::eyre!("no input device available"))?;
+    let stream_config = cpal::StreamConfig {
+        channels: config.channels,
+        sample_rate: cpal::SampleRate(config.sample_rate),
+        buffer_size: cpal::BufferSize::Default,
+    };
+
+    let ring_buffer = HeapRb::<f32>::new(config.sample_rate as usize * config.channels as usize);
+    let (mut producer, mut consumer) = ring_buffer.split();
+
+    let stream = device.build_input_stream(
+        &stream_config,
+        move |data: &[f32], _: &cpal::InputCallbackInfo| {
+            producer.push_slice(data);
+        },
+        |err| log::info!("an error occurred on the audio input stream: {}", err),
+        None,
+    )?;
+
+    stream.play()?;
+
+    let mut frame_buffer = vec![0.0f32; config.sample_rate as usize / 50]; // 20ms frame
+
+    while is_streaming.load(Ordering::Relaxed) {
+        let mut frame = Mat::default();
+        {
+            let mut camera = camera.lock();
+            camera.read(&mut frame)?;
+        }
+
+        if !frame.empty() {
+            let ascii_frame = ascii::process_frame(&frame, &ascii_ramp, config.edge_overlay, false)?;
+            let ascii_image =
+                ascii::render_ascii_frame(&ascii_frame.plain_text(), frame_size, config.font_scale)?;
+
+            if let Err(e) = publisher.push_video_frame(&ascii_image) {
+                log::info!("stream publish error on video frame: {:?}", e);
+                if !reconnect_publisher(&mut publisher, &url, frame_size, &config, &connection_state) {
+                    is_streaming.store(false, Ordering::Relaxed);
+                    break;
+                }
+            }
+        }
+
+        while consumer.len() >= frame_buffer.len() {
+            consumer.pop_slice(&mut frame_buffer);
+
+            if let Err(e) = publisher.push_audio_samples(&frame_buffer) {
+                log::info!("stream publish error on audio samples: {:?}", e);
+                if !reconnect_publisher(&mut publisher, &url, frame_size, &config, &connection_state) {
+                    is_streaming.store(false, Ordering::Relaxed);
+                    break;
+                }
+            }
+        }
+
+        thread::sleep(Duration::from_millis(1000 / config.target_fps));
+    }
+
+    drop(stream);
+    publisher.disconnect()?;
+
+    Ok(())
+}
+
+fn run_app<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    camera: Arc<Mutex<VideoCapture>>,
+    stream_url: Option<String>,
+    config: RecordingConfig,
+) -> io::Result<()> {
+    let mut last_frame_time = Instant::now();
+    let target_frame_time = Duration::from_micros(1_000_000 / config.target_fps);
+    let is_recording = Arc::new(AtomicBool::new(false));
+    let recording_thread: Arc<Mutex<Option<JoinHandle<Result<()>>>>> = Arc::new(Mutex::new(None));
+    let is_streaming = Arc::new(AtomicBool::new(false));
+    let streaming_thread: Arc<Mutex<Option<JoinHandle<Result<()>>>>> = Arc::new(Mutex::new(None));
+    let connection_state = Arc::new(Mutex::new(ConnectionState::Connecting));
+
+    loop {
+        let frame_start = Instant::now();
+
+        // get terminal size
+        let size = terminal.size()?;
+        let term_width = size.width as i32;
+        let term_height = size.height as i32;
+
+        // process frame and update app
+        let mut frame = Mat::default();
+        {
+            let mut camera = camera.lock();
+            camera
+                .read(&mut frame)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        }
+
+        if !frame.empty() {
+            let mut resized_frame = Mat::default();
+
+            imgproc::resize(
+                &frame,
+                &mut resized_frame,
+                Size::new(term_width, term_height),
+                0.0,
+                0.0,
+                imgproc::INTER_LINEAR,
+            )
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+
+            app.update(&resized_frame)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        }
+
+        terminal.draw(|f| {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .margin(1)
+                .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+                .split(f.area());
+
+            let status = match app.mode {
+                AppMode::Idle => "Idle",
+                AppMode::Recording => "Recording",
+                AppMode::Streaming => app.stream_state.map(ConnectionState::label).unwrap_or("Streaming"),
+            };
+            let stats_text = format!("FPS: {:.2} | Status: {}", app.fps, status);
+            let stats_paragraph = Paragraph::new(stats_text)
+                .style(Style::default().fg(Color::Cyan))
+                .block(Block::default().borders(Borders::ALL).title("Stats"));
+
+            f.render_widget(stats_paragraph, chunks[0]);
+
+            let ascii_paragraph = Paragraph::new(app.ascii_frame.to_text())
+                .block(Block::default().borders(Borders::ALL).title("ASCII Webcam"));
+
+            f.render_widget(ascii_paragraph, chunks[1]);
+        })?;
+
+        if event::poll(Duration::from_millis(1))? {
+            if let Event::Key(key) = event::read()? {
+                match key.code {
+                    KeyCode::Char('q') => {
+                        // stop recording/streaming if either is ongoing
+                        if app.mode == AppMode::Recording {
+                            is_recording.store(false, Ordering::Relaxed);
+
+                            if let Some(handle) = recording_thread.lock().take() {
+                                match handle.join() {
+                                    Ok(result) => {
+                                        if let Err(e) = result {
+                                            log::info!("recording error: {:?}", e);
+                                        }
+                                    }
+                                    Err(e) => {
+                                        log::info!("failed to join recording thread: {:?}", e)
+                                    }
+                                }
+                            }
+                        } else if app.mode == AppMode::Streaming {
+                            is_streaming.store(false, Ordering::Relaxed);
+
+                            if let Some(handle) = streaming_thread.lock().take() {
+                                match handle.join() {
+                                    Ok(result) => {
+                                        if let Err(e) = result {
+                                            log::info!("streaming error: {:?}", e);
+                                        }
+                                    }
+                                    Err(e) => {
+                                        log::info!("failed to join streaming thread: {:?}", e)
+                                    }
+                                }
+                            }
+                        }
+
+                        return Ok(());
+                    }
+                    KeyCode::Char('r') => {
+                        if app.mode == AppMode::Idle {
+                            app.mode = AppMode::Recording;
+
+                            is_recording.store(true, Ordering::Relaxed);
+                            let camera_clone = camera.clone();
+                            let is_recording_clone = is_recording.clone();
+                            let config_clone = config.clone();
+                            let handle = thread::spawn(move || {
+                                start_recording(camera_clone, is_recording_clone, config_clone)
+                            });
+
+                            *recording_thread.lock() = Some(handle);
+                        } else if app.mode == AppMode::Recording {
+                            app.mode = AppMode::Idle;
+
+                            is_recording.store(false, Ordering::Relaxed);
+
+                            if let Some(handle) = recording_thread.lock().take() {
+                                match handle.join() {
+                                    Ok(result) => {
+                                        if let Err(e) = result {
+                                            log::info!("recording error: {:?}", e);
+                                        }
+                                    }
+                                    Err(e) => {
+                                        log::info!("failed to join recording thread: {:?}", e)
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    KeyCode::Char('s') => {
+                        if app.mode == AppMode::Idle {
+                            if let Some(url) = stream_url.clone() {
+                                app.mode = AppMode::Streaming;
+                                app.stream_state = Some(ConnectionState::Connecting);
+
+                                is_streaming.store(true, Ordering::Relaxed);
+                                let camera_clone = camera.clone();
+                                let is_streaming_clone = is_streaming.clone();
+                                let connection_state_clone = connection_state.clone();
+                                let config_clone = config.clone();
+                                let handle = thread::spawn(move || {
+                                    start_streaming(
+                                        camera_clone,
+                                        is_streaming_clone,
+                                        url,
+                                        connection_state_clone,
+                                        config_clone,
+                                    )
+                                });
+
+                                *streaming_thread.lock() = Some(handle);
+                            }
+                        } else if app.mode == AppMode::Streaming {
+                            app.mode = AppMode::Idle;
+                            app.stream_state = None;
+
+                            is_streaming.store(false, Ordering::Relaxed);
+
+                            if let Some(handle) = streaming_thread.lock().take() {
+                                match handle.join() {
+                                    Ok(result) => {
+                                        if let Err(e) = result {
+                                            log::info!("streaming error: {:?}", e);
+                                        }
+                                    }
+                                    Err(e) => {
+                                        log::info!("failed to join streaming thread: {:?}", e)
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if app.mode == AppMode::Streaming {
+            app.stream_state = Some(*connection_state.lock());
+        }
+
+        let current_frame_time = Instant::now();
+
+        app.fps = 1.0
+            / current_frame_time
+                .duration_since(last_frame_time)
+                .as_secs_f64();
+        last_frame_time = current_frame_time;
+
+        let processing_time = frame_start.elapsed();
+        if processing_time < target_frame_time {
+            thread::sleep(target_frame_time - processing_time);
+        }
+    }
+}
+
+/// Parses `--stream <url>` off the command line. There's no broader CLI
+/// surface yet, so this stays a plain scan rather than pulling in an args
+/// parsing crate.
+fn parse_stream_url() -> Option<String> {
+    let args: Vec<String> = env::args().collect();
+    let flag_index = args.iter().position(|arg| arg == "--stream")?;
+
+    args.get(flag_index + 1).cloned()
+}
+
+fn reset_terminal() -> Result<()> {
+    disable_raw_mode()?;
+
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    color_eyre::install()?;
+    setup_logging().ok();
+
+    // let original_hook = panic::take_hook();
+    // panic::set_hook(Box::new(move |panic_info| {
+    //     reset_terminal().expect("failed to reset terminal");
+    //
+    //     original_hook(panic_info);
+    // }));
+
+    // setup terminal
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    // initialize camera
+    let camera = Arc::new(Mutex::new(VideoCapture::new(0, CAP_ANY)?));
+    let config = RecordingConfig::load()?;
+    let mut app = App::new(&config);
+    let stream_url = parse_stream_url();
+
+    let res = run_app(&mut terminal, &mut app, camera.clone(), stream_url, config);
+
+    // restore terminal
+    reset_terminal()?;
+
+    if let Err(err) = res {
+        println!("Error: {:?}", err);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,129 @@
+//! Scene-boundary detection for chunked parallel encoding.
+//!
+//! Each rendered frame is reduced to a downscaled 32x32 luma thumbnail. The
+//! normalized sum-of-absolute-differences against the previous thumbnail is
+//! compared to `threshold`; once the current scene has run for at least
+//! `min_scene_len` frames and that threshold is crossed, the triggering
+//! frame is reported as the start of a new scene.
+
+use opencv::core::Size;
+use opencv::{imgproc, prelude::*};
+
+const THUMB_SIZE: i32 = 32;
+
+pub struct SceneDetector {
+    threshold: f64,
+    min_scene_len: usize,
+    prev_thumb: Option<Mat>,
+    run_len: usize,
+}
+
+impl SceneDetector {
+    pub fn new(threshold: f64, min_scene_len: usize) -> Self {
+        Self {
+            threshold,
+            min_scene_len,
+            prev_thumb: None,
+            run_len: 0,
+        }
+    }
+
+    /// Feed one rendered frame. Returns `true` if this frame should start a
+    /// new scene (the caller is responsible for closing out whatever frames
+    /// it had buffered for the scene that just ended).
+    pub fn is_boundary(&mut self, frame: &Mat) -> opencv::Result<bool> {
+        let thumb = downscale_luma(frame)?;
+
+        let boundary = match &self.prev_thumb {
+            Some(prev) if self.run_len >= self.min_scene_len => {
+                normalized_sad(prev, &thumb)? > self.threshold
+            }
+            _ => false,
+        };
+
+        self.prev_thumb = Some(thumb);
+        // the boundary frame is the first frame of the new scene
+        self.run_len = if boundary { 1 } else { self.run_len + 1 };
+
+        Ok(boundary)
+    }
+}
+
+fn downscale_luma(frame: &Mat) -> opencv::Result<Mat> {
+    let mut gray = Mat::default();
+    imgproc::cvt_color(frame, &mut gray, imgproc::COLOR_BGR2GRAY, 0)?;
+
+    let mut thumb = Mat::default();
+    imgproc::resize(
+        &gray,
+        &mut thumb,
+        Size::new(THUMB_SIZE, THUMB_SIZE),
+        0.0,
+        0.0,
+        imgproc::INTER_AREA,
+    )?;
+
+    Ok(thumb)
+}
+
+fn normalized_sad(prev: &Mat, current: &Mat) -> opencv::Result<f64> {
+    let mut diff = Mat::default();
+    opencv::core::absdiff(prev, current, &mut diff)?;
+
+    let sum = opencv::core::sum_elems(&diff)?[0];
+    let pixel_count = (THUMB_SIZE * THUMB_SIZE) as f64;
+
+    Ok(sum / pixel_count / 255.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opencv::core::{Scalar, CV_8UC1, CV_8UC3};
+
+    fn solid_frame(value: f64) -> Mat {
+        Mat::new_rows_cols_with_default(64, 64, CV_8UC3, Scalar::all(value)).unwrap()
+    }
+
+    fn solid_thumb(value: f64) -> Mat {
+        Mat::new_rows_cols_with_default(THUMB_SIZE, THUMB_SIZE, CV_8UC1, Scalar::all(value)).unwrap()
+    }
+
+    #[test]
+    fn normalized_sad_is_zero_for_identical_thumbnails() {
+        let a = solid_thumb(100.0);
+        let b = solid_thumb(100.0);
+
+        assert_eq!(normalized_sad(&a, &b).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn normalized_sad_is_one_for_fully_opposite_thumbnails() {
+        let a = solid_thumb(0.0);
+        let b = solid_thumb(255.0);
+
+        assert_eq!(normalized_sad(&a, &b).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn is_boundary_ignores_changes_before_min_scene_len() {
+        let mut detector = SceneDetector::new(0.1, 3);
+        let dark = solid_frame(0.0);
+        let bright = solid_frame(255.0);
+
+        assert!(!detector.is_boundary(&dark).unwrap());
+        assert!(!detector.is_boundary(&bright).unwrap());
+        assert!(!detector.is_boundary(&bright).unwrap());
+    }
+
+    #[test]
+    fn is_boundary_fires_once_min_scene_len_and_threshold_are_met() {
+        let mut detector = SceneDetector::new(0.1, 2);
+        let dark = solid_frame(0.0);
+        let bright = solid_frame(255.0);
+
+        assert!(!detector.is_boundary(&dark).unwrap());
+        assert!(!detector.is_boundary(&dark).unwrap());
+        assert!(detector.is_boundary(&bright).unwrap());
+    }
+}
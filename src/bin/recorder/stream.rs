@@ -0,0 +1,174 @@
+//! Live publish path: push the same rendered ASCII frames and mic audio
+//! used for local recording to a remote media server instead of a file,
+//! authenticating with a short-lived JWT the way a LiveKit-style ingest
+//! server expects.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use color_eyre::{eyre, Result};
+use hmac::{Hmac, Mac};
+use opencv::{core::Size, prelude::*};
+use serde::Serialize;
+use sha2::Sha256;
+
+use crate::encoder::AvMuxer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    Connecting,
+    Live,
+    Reconnecting,
+    Disconnected,
+}
+
+impl ConnectionState {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConnectionState::Connecting => "Connecting",
+            ConnectionState::Live => "Live",
+            ConnectionState::Reconnecting => "Reconnecting",
+            ConnectionState::Disconnected => "Disconnected",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct VideoGrant<'a> {
+    room: &'a str,
+    #[serde(rename = "roomJoin")]
+    room_join: bool,
+}
+
+#[derive(Serialize)]
+struct Claims<'a> {
+    iss: &'a str,
+    sub: &'a str,
+    exp: u64,
+    video: VideoGrant<'a>,
+}
+
+/// Signs a JWT (HMAC-SHA256 over the room/identity claims) for a LiveKit-style
+/// ingest server. `expires_at` is a Unix timestamp, not a TTL, so callers
+/// supply it rather than this function reaching for the clock itself.
+pub fn sign_stream_token(api_key: &str, api_secret: &str, room: &str, identity: &str, expires_at: u64) -> Result<String> {
+    let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"HS256","typ":"JWT"}"#);
+
+    let claims = Claims {
+        iss: api_key,
+        sub: identity,
+        exp: expires_at,
+        video: VideoGrant {
+            room,
+            room_join: true,
+        },
+    };
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims)?);
+    let signing_input = format!("{header}.{payload}");
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(api_secret.as_bytes())
+        .map_err(|_| eyre::eyre!("stream api secret is not a valid HMAC key"))?;
+    mac.update(signing_input.as_bytes());
+    let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+
+    Ok(format!("{signing_input}.{signature}"))
+}
+
+/// Publishes rendered ASCII video + mic audio to a remote media server by
+/// opening the existing `AvMuxer` pipeline against a network URL instead of
+/// a local file path, and tracks connection state for display in the TUI.
+pub struct StreamPublisher {
+    muxer: AvMuxer,
+    pub state: ConnectionState,
+}
+
+impl StreamPublisher {
+    pub fn connect(
+        url: &str,
+        token: &str,
+        frame_size: Size,
+        fps: u64,
+        sample_rate: u32,
+        channels: u16,
+    ) -> Result<Self> {
+        let authenticated_url = format!("{url}?token={token}");
+        let muxer = AvMuxer::new_streaming(
+            &authenticated_url,
+            "rtsp",
+            (frame_size.width, frame_size.height),
+            fps,
+            sample_rate,
+            channels,
+        )?;
+
+        Ok(Self {
+            muxer,
+            state: ConnectionState::Live,
+        })
+    }
+
+    pub fn push_video_frame(&mut self, frame: &Mat) -> Result<()> {
+        self.muxer.push_video_frame(frame)
+    }
+
+    pub fn push_audio_samples(&mut self, samples: &[f32]) -> Result<()> {
+        self.muxer.push_audio_samples(samples)
+    }
+
+    pub fn disconnect(self) -> Result<()> {
+        self.muxer.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_stream_token_has_three_base64url_segments() {
+        let token = sign_stream_token("key", "secret", "room", "identity", 123).unwrap();
+        let segments: Vec<&str> = token.split('.').collect();
+
+        assert_eq!(segments.len(), 3);
+        for segment in segments {
+            assert!(URL_SAFE_NO_PAD.decode(segment).is_ok());
+        }
+    }
+
+    #[test]
+    fn sign_stream_token_embeds_the_given_claims() {
+        let token = sign_stream_token("a-key", "secret", "a-room", "an-identity", 456).unwrap();
+        let payload = token.split('.').nth(1).unwrap();
+        let claims: serde_json::Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).unwrap()).unwrap();
+
+        assert_eq!(claims["iss"], "a-key");
+        assert_eq!(claims["sub"], "an-identity");
+        assert_eq!(claims["exp"], 456);
+        assert_eq!(claims["video"]["room"], "a-room");
+        assert_eq!(claims["video"]["roomJoin"], true);
+    }
+
+    #[test]
+    fn sign_stream_token_signature_round_trips_through_hmac() {
+        let token = sign_stream_token("key", "secret", "room", "identity", 789).unwrap();
+        let mut segments = token.split('.');
+        let (header, payload, signature) = (
+            segments.next().unwrap(),
+            segments.next().unwrap(),
+            segments.next().unwrap(),
+        );
+
+        let signing_input = format!("{header}.{payload}");
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+        mac.update(signing_input.as_bytes());
+        let expected = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+
+        assert_eq!(signature, expected);
+    }
+
+    #[test]
+    fn sign_stream_token_accepts_an_empty_secret() {
+        // HMAC keys can be any length, including zero, so an empty
+        // `STREAM_API_SECRET` env var should produce a token rather than
+        // erroring - guards against assuming `new_from_slice` rejects it.
+        assert!(sign_stream_token("key", "", "room", "identity", 0).is_ok());
+    }
+}